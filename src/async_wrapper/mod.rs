@@ -1,11 +1,12 @@
 use std::fs::remove_file;
+use std::os::unix::io::{AsRawFd, RawFd};
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncReadExt;
 use tokio::net::UnixListener;
 
 use logs::info;
 
-use crate::get_full_path;
+use crate::{get_full_path, fd_passing, no_sigpipe, peer_cred, UCred};
 
 pub struct AsyncConnection {
     stream: tokio::net::UnixStream,
@@ -13,9 +14,28 @@ pub struct AsyncConnection {
 
 impl AsyncConnection {
     pub fn new(stream: tokio::net::UnixStream) -> Self {
+        // Best-effort: on platforms without MSG_NOSIGNAL, writes to a dead peer still raise
+        // SIGPIPE until this is set, but there's no fallback worth failing the connection over.
+        let _ = no_sigpipe::set_nosigpipe(stream.as_raw_fd());
         Self { stream }
     }
 
+    /// Writes `data` to the socket, retrying on `WouldBlock` until it's writable again, with
+    /// `SIGPIPE` suppressed so a write to a peer that has disconnected returns a recoverable
+    /// [`BrokenPipe`](std::io::ErrorKind::BrokenPipe) error instead of killing the process.
+    async fn write_all_no_sigpipe(&mut self, mut data: &[u8]) -> Result<(), std::io::Error> {
+        let raw_fd = self.stream.as_raw_fd();
+        while !data.is_empty() {
+            self.stream.writable().await?;
+            match no_sigpipe::send_once(raw_fd, data) {
+                Ok(n) => data = &data[n..],
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e)
+            }
+        }
+        Ok(())
+    }
+
     pub async fn read_raw(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
         match self.stream.read(buf).await {
             Ok(bytes_read) => {
@@ -56,7 +76,76 @@ impl AsyncConnection {
 
     pub async fn send(&mut self, response: impl Into<String>) -> Result<(), std::io::Error> {
         let response: String = response.into();
-        self.stream.write_all(&Vec::from(response)).await
+        self.write_all_no_sigpipe(&Vec::from(response)).await
+    }
+
+    /// Returns the uid, gid and (on Linux) pid of the process on the other end of this connection.
+    pub fn peer_cred(&self) -> Result<UCred, std::io::Error> {
+        peer_cred::peer_cred(self.stream.as_raw_fd())
+    }
+
+    /// Sends `data` together with `fds` as `SCM_RIGHTS` ancillary data, handing the open
+    /// file descriptors over to the process on the other end of the connection.
+    ///
+    /// `data` must contain at least one byte; some kernels refuse to carry ancillary data on
+    /// an empty payload.
+    pub async fn send_with_fds(&mut self, data: &[u8], fds: &[RawFd]) -> Result<usize, std::io::Error> {
+        let raw_fd = self.stream.as_raw_fd();
+        loop {
+            self.stream.writable().await?;
+            match fd_passing::send_with_fds(raw_fd, data, fds) {
+                Ok(n) => return Ok(n),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e)
+            }
+        }
+    }
+
+    /// Receives data together with any `SCM_RIGHTS` fds attached to it.
+    ///
+    /// Up to `fd_buf.capacity()` fds are accepted; received fds are owned by the caller, who
+    /// is responsible for closing them.
+    pub async fn recv_with_fds(&mut self, buf: &mut [u8], fd_buf: &mut Vec<RawFd>) -> Result<usize, std::io::Error> {
+        let raw_fd = self.stream.as_raw_fd();
+        loop {
+            self.stream.readable().await?;
+            match fd_passing::recv_with_fds(raw_fd, buf, fd_buf) {
+                Ok(n) => return Ok(n),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e)
+            }
+        }
+    }
+
+    /// Writes `data` as a length-prefixed frame: a 4-byte big-endian length followed by the payload.
+    ///
+    /// Pairs with [`recv_framed`](AsyncConnection::recv_framed) so a single read on a stream
+    /// socket can't truncate or coalesce messages.
+    pub async fn send_framed(&mut self, data: &[u8]) -> Result<(), std::io::Error> {
+        let len = u32::try_from(data.len())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "frame too large to fit in a 4-byte length prefix"))?;
+        self.write_all_no_sigpipe(&len.to_be_bytes()).await?;
+        self.write_all_no_sigpipe(data).await
+    }
+
+    /// Reads a single length-prefixed frame written by [`send_framed`](AsyncConnection::send_framed).
+    ///
+    /// Returns an error of kind [`InvalidData`](std::io::ErrorKind::InvalidData) instead of
+    /// allocating if the declared length exceeds `max_frame_size`, guarding against a hostile
+    /// or corrupted length prefix.
+    pub async fn recv_framed(&mut self, max_frame_size: usize) -> Result<Vec<u8>, std::io::Error> {
+        let mut len_buf = [0u8; 4];
+        self.stream.read_exact(&mut len_buf).await?;
+        let len = u32::from_be_bytes(len_buf) as usize;
+
+        if len > max_frame_size {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("frame of {len} bytes exceeds the max frame size of {max_frame_size} bytes")));
+        }
+
+        let mut buf = vec![0u8; len];
+        self.stream.read_exact(&mut buf).await?;
+        Ok(buf)
     }
 }
 
@@ -0,0 +1,109 @@
+//! Passing open file descriptors between processes over a Unix domain socket, using
+//! `SCM_RIGHTS` ancillary data.
+
+use std::io;
+use std::mem::{size_of, size_of_val, zeroed};
+use std::os::unix::io::RawFd;
+use std::ptr;
+
+/// Sends `data` together with `fds` as `SCM_RIGHTS` ancillary data.
+///
+/// `data` must contain at least one byte; some kernels refuse to carry ancillary data on an
+/// empty payload.
+pub(crate) fn send_with_fds(fd: RawFd, data: &[u8], fds: &[RawFd]) -> io::Result<usize>
+{
+    if data.is_empty()
+    {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "payload must contain at least one byte to carry fds"));
+    }
+
+    let cmsg_space = unsafe { libc::CMSG_SPACE(size_of_val(fds) as u32) } as usize;
+    let mut cmsg_buf = vec![0u8; cmsg_space];
+
+    let mut iov = libc::iovec
+    {
+        iov_base: data.as_ptr() as *mut libc::c_void,
+        iov_len: data.len()
+    };
+
+    let mut msg: libc::msghdr = unsafe { zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg_buf.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = cmsg_space as _;
+
+    unsafe
+    {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of_val(fds) as u32) as _;
+        ptr::copy_nonoverlapping(fds.as_ptr(), libc::CMSG_DATA(cmsg) as *mut RawFd, fds.len());
+    }
+
+    let sent = unsafe { libc::sendmsg(fd, &msg, 0) };
+    if sent < 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(sent as usize)
+}
+
+/// Receives data together with any `SCM_RIGHTS` fds attached to it.
+///
+/// Up to `fd_buf.capacity()` fds are accepted; if the kernel had to truncate the ancillary
+/// data (`MSG_CTRUNC`) because the buffer was too small, this returns [`io::ErrorKind::InvalidData`].
+/// Received fds are appended to `fd_buf` and are owned by the caller, who is responsible for
+/// closing them.
+pub(crate) fn recv_with_fds(fd: RawFd, buf: &mut [u8], fd_buf: &mut Vec<RawFd>) -> io::Result<usize>
+{
+    let fd_bytes = size_of_val(fd_buf.spare_capacity_mut()) as u32;
+    let cmsg_space = unsafe { libc::CMSG_SPACE(fd_bytes) } as usize;
+    // CMSG_LEN rather than CMSG_SPACE, so the alignment padding can't make room for more fds
+    // than fd_buf has spare capacity for.
+    let cmsg_len = unsafe { libc::CMSG_LEN(fd_bytes) } as usize;
+    let mut cmsg_buf = vec![0u8; cmsg_space];
+
+    let mut iov = libc::iovec
+    {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len()
+    };
+
+    let mut msg: libc::msghdr = unsafe { zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg_buf.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = cmsg_len as _;
+
+    let received = unsafe { libc::recvmsg(fd, &mut msg, 0) };
+    if received < 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+
+    if msg.msg_flags & libc::MSG_CTRUNC != 0
+    {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "received fds were truncated, fd_buf was too small"));
+    }
+
+    unsafe
+    {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null()
+        {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS
+            {
+                let count = ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize) / size_of::<libc::c_int>();
+                let data_ptr = libc::CMSG_DATA(cmsg) as *const RawFd;
+                for i in 0..count
+                {
+                    fd_buf.push(*data_ptr.add(i));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    Ok(received as usize)
+}
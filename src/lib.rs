@@ -37,21 +37,39 @@
 //! ```
 #[cfg(feature="async")]
 pub mod async_wrapper;
+pub mod server;
+mod peer_cred;
+mod fd_passing;
+mod no_sigpipe;
 
 use std::fs::create_dir_all;
 use std::os::unix::net::{UnixStream, UnixListener};
-use std::io::{ Read, Write };
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::io::{ Read, Write, IoSlice, IoSliceMut };
+use std::net::Shutdown;
 use std::path::Path;
 use std::time::Duration;
 use std::fs::remove_file;
 
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+#[cfg(target_os = "linux")]
+use std::os::unix::net::SocketAddr;
+
 use logs::{info, error};
 
+pub use peer_cred::UCred;
+pub use server::{RawListener, StreamData};
+
 /// Default path for the sockets
-/// 
+///
 /// Used when the user doesn't provide a full path for the socket.
 pub const SOCKET_PATH: &str = "/tmp";
 
+/// A sane default for the `max_frame_size` guard on [`recv_framed`](Connection::recv_framed),
+/// used when the caller doesn't need a tighter bound.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 8 * 1024 * 1024;
+
 fn get_full_path(path: impl Into<String>) -> String
 {
     let path: String = path.into();
@@ -101,6 +119,9 @@ impl Connection
 {
     pub fn new(stream: UnixStream) -> Self
     {
+        // Best-effort: on platforms without MSG_NOSIGNAL, writes to a dead peer still raise
+        // SIGPIPE until this is set, but there's no fallback worth failing the connection over.
+        let _ = no_sigpipe::set_nosigpipe(stream.as_raw_fd());
         Self { stream }
     }
 
@@ -123,15 +144,133 @@ impl Connection
         Ok(T::from(message.to_string()))
     }
     
+    /// Sends `response`, with `SIGPIPE` suppressed so a write to a peer that has disconnected
+    /// returns a recoverable [`BrokenPipe`](std::io::ErrorKind::BrokenPipe) error instead of
+    /// killing the process.
     pub fn send_raw(&mut self, response: Vec<u8>) -> Result<(), std::io::Error>
     {
-        self.stream.write_all(&response.as_slice())
-    } 
+        no_sigpipe::write_all(self.stream.as_raw_fd(), response.as_slice())
+    }
 
+    /// Sends `response`, with `SIGPIPE` suppressed so a write to a peer that has disconnected
+    /// returns a recoverable [`BrokenPipe`](std::io::ErrorKind::BrokenPipe) error instead of
+    /// killing the process.
     pub fn send(&mut self, response: impl Into<String>) -> Result<(), std::io::Error>
     {
         let response: String = response.into();
-        self.stream.write_all(&Vec::from(response))
+        no_sigpipe::write_all(self.stream.as_raw_fd(), &Vec::from(response))
+    }
+
+    /// Returns the uid, gid and (on Linux) pid of the process on the other end of this connection.
+    ///
+    /// This lets a server make privilege decisions about a peer without trusting the
+    /// filesystem permissions of the socket alone.
+    pub fn peer_cred(&self) -> Result<UCred, std::io::Error>
+    {
+        peer_cred::peer_cred(self.stream.as_raw_fd())
+    }
+
+    /// Sends `data` together with `fds` as `SCM_RIGHTS` ancillary data, handing the open
+    /// file descriptors over to the process on the other end of the connection.
+    ///
+    /// `data` must contain at least one byte; some kernels refuse to carry ancillary data on
+    /// an empty payload.
+    pub fn send_with_fds(&mut self, data: &[u8], fds: &[RawFd]) -> Result<usize, std::io::Error>
+    {
+        fd_passing::send_with_fds(self.stream.as_raw_fd(), data, fds)
+    }
+
+    /// Receives data together with any `SCM_RIGHTS` fds attached to it.
+    ///
+    /// Up to `fd_buf.capacity()` fds are accepted; received fds are owned by the caller, who
+    /// is responsible for closing them.
+    pub fn recv_with_fds(&mut self, buf: &mut [u8], fd_buf: &mut Vec<RawFd>) -> Result<usize, std::io::Error>
+    {
+        fd_passing::recv_with_fds(self.stream.as_raw_fd(), buf, fd_buf)
+    }
+
+    /// Sets a timeout on [`read`](Connection::read)/[`read_raw`](Connection::read_raw) calls.
+    ///
+    /// A call that doesn't complete within `timeout` returns an error of kind
+    /// [`WouldBlock`](std::io::ErrorKind::WouldBlock) or [`TimedOut`](std::io::ErrorKind::TimedOut).
+    /// Passing `None` disables the timeout, which is the default.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), std::io::Error>
+    {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    /// Sets a timeout on [`send`](Connection::send)/[`send_raw`](Connection::send_raw) calls.
+    ///
+    /// A call that doesn't complete within `timeout` returns an error of kind
+    /// [`WouldBlock`](std::io::ErrorKind::WouldBlock) or [`TimedOut`](std::io::ErrorKind::TimedOut).
+    /// Passing `None` disables the timeout, which is the default.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<(), std::io::Error>
+    {
+        self.stream.set_write_timeout(timeout)
+    }
+
+    /// Puts the underlying socket in non-blocking mode, so reads/writes that would block
+    /// instead return an error of kind [`WouldBlock`](std::io::ErrorKind::WouldBlock).
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), std::io::Error>
+    {
+        self.stream.set_nonblocking(nonblocking)
+    }
+
+    /// Writes `data` as a length-prefixed frame: a 4-byte big-endian length followed by the payload.
+    ///
+    /// Pairs with [`recv_framed`](Connection::recv_framed) so a single `read`/`write_all` on a
+    /// stream socket can't truncate or coalesce messages.
+    pub fn send_framed(&mut self, data: &[u8]) -> Result<(), std::io::Error>
+    {
+        let len = u32::try_from(data.len())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "frame too large to fit in a 4-byte length prefix"))?;
+        let raw_fd = self.stream.as_raw_fd();
+        no_sigpipe::write_all(raw_fd, &len.to_be_bytes())?;
+        no_sigpipe::write_all(raw_fd, data)
+    }
+
+    /// Reads a single length-prefixed frame written by [`send_framed`](Connection::send_framed).
+    ///
+    /// Returns an error of kind [`InvalidData`](std::io::ErrorKind::InvalidData) instead of
+    /// allocating if the declared length exceeds `max_frame_size`, guarding against a hostile
+    /// or corrupted length prefix.
+    pub fn recv_framed(&mut self, max_frame_size: usize) -> Result<Vec<u8>, std::io::Error>
+    {
+        let mut len_buf = [0u8; 4];
+        self.stream.read_exact(&mut len_buf)?;
+        let len = u32::from_be_bytes(len_buf) as usize;
+
+        if len > max_frame_size
+        {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("frame of {len} bytes exceeds the max frame size of {max_frame_size} bytes")));
+        }
+
+        let mut buf = vec![0u8; len];
+        self.stream.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// Lets a client close its write half to signal end-of-request while still reading the
+    /// response, a common request/response idiom.
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), std::io::Error>
+    {
+        self.stream.shutdown(how)
+    }
+
+    /// Writes from multiple buffers in a single syscall, without an intermediate copy, with
+    /// `SIGPIPE` suppressed the same way [`send`](Connection::send) is.
+    pub fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, std::io::Error>
+    {
+        no_sigpipe::send_vectored_once(self.stream.as_raw_fd(), bufs)
+    }
+
+    /// Reads into multiple buffers in a single syscall, without an intermediate copy.
+    pub fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, std::io::Error>
+    {
+        self.stream.read_vectored(bufs)
     }
 }
 
@@ -140,6 +279,17 @@ pub fn new_client(path: impl Into<String>) -> Result<UnixStream, std::io::Error>
     UnixStream::connect(get_full_path(path))
 }
 
+/// Connects to a server bound to a Linux abstract-namespace socket (see [`Server::bind_abstract`]).
+///
+/// Abstract sockets have no filesystem path, so `name` is used as-is rather than resolved
+/// against [`SOCKET_PATH`].
+#[cfg(target_os = "linux")]
+pub fn new_client_abstract(name: &str) -> Result<UnixStream, std::io::Error>
+{
+    let addr = SocketAddr::from_abstract_name(name.as_bytes())?;
+    UnixStream::connect_addr(&addr)
+}
+
 /// Sends data to the server and waits for a response if needed. 
 /// 
 /// It cant use the connection created when the client is instantiated, it is supposed to be used when
@@ -189,12 +339,22 @@ pub struct Server
 {
     pub raw_path: String,
     pub socket_name: String,
-    listener: UnixListener
+    listener: UnixListener,
+    allowed_uids: Option<Vec<u32>>
 }
 
 impl Server
 {
     pub fn new(socket_name: &str) -> Result<Self, std::io::Error>
+    {
+        Self::new_with_allowed_uids(socket_name, None)
+    }
+
+    /// Creates a new server, rejecting any connection whose peer uid (see [`peer_cred`](Connection::peer_cred))
+    /// isn't in `allowed_uids`.
+    ///
+    /// Passing `None` disables the check, matching the behaviour of [`new`](Server::new).
+    pub fn new_with_allowed_uids(socket_name: &str, allowed_uids: Option<Vec<u32>>) -> Result<Self, std::io::Error>
     {
         let server_path = if socket_name.starts_with('/')
         {
@@ -205,11 +365,11 @@ impl Server
             format!("{SOCKET_PATH}/{socket_name}")
         };
         info!("Creating server {:?}", server_path);
-        
+
         match remove_file(&server_path)
         {
             Ok(_) => { },
-            Err(_) => 
+            Err(_) =>
             {
                 error!("Socket not found, creating a new one...");
             }
@@ -218,7 +378,28 @@ impl Server
         {
             raw_path: server_path.to_string(),
             socket_name: socket_name.to_string(),
-            listener: UnixListener::bind(server_path)?
+            listener: UnixListener::bind(server_path)?,
+            allowed_uids
+        })
+    }
+
+    /// Creates a new server bound to a Linux abstract-namespace socket instead of a filesystem path.
+    ///
+    /// Abstract sockets (names in the `@`-prefixed, no-filesystem address space) are cleaned
+    /// up by the kernel when the listener is closed, so unlike [`new`](Server::new) this skips
+    /// the `remove_file` step entirely.
+    #[cfg(target_os = "linux")]
+    pub fn bind_abstract(name: &str) -> Result<Self, std::io::Error>
+    {
+        info!("Creating abstract server {:?}", name);
+
+        let addr = SocketAddr::from_abstract_name(name.as_bytes())?;
+        Ok(Self
+        {
+            raw_path: format!("@{name}"),
+            socket_name: name.to_string(),
+            listener: UnixListener::bind_addr(&addr)?,
+            allowed_uids: None
         })
     }
 
@@ -236,9 +417,9 @@ impl Server
     /// fn handle_data(data: &[u8], mut stream_data: StreamData)
     /// {
     ///     let msg = format!("Received {:?}", String::from_utf8_lossy(data));
-    ///     stream_data.stream.write_all(msg.as_bytes()).unwrap();
+    ///     stream_data.send(msg).unwrap();
     /// }
-    /// 
+    ///
     /// fn flow(mut server: Server) -> Result<(), io::Error>
     /// {
     ///     let mut buf = vec![0u8; 512];
@@ -253,8 +434,22 @@ impl Server
     /// }
     pub fn wait_connection(&mut self) -> Result<Connection, std::io::Error>
     {
-        let (stream, _) = self.listener.accept()?;
-        
-        Ok(Connection::new(stream))
+        loop
+        {
+            let (stream, _) = self.listener.accept()?;
+            let connection = Connection::new(stream);
+
+            if let Some(allowed_uids) = &self.allowed_uids
+            {
+                let cred = connection.peer_cred()?;
+                if !allowed_uids.contains(&cred.uid)
+                {
+                    error!("Rejected connection from uid {} not in the allow-list", cred.uid);
+                    continue;
+                }
+            }
+
+            return Ok(connection);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+//! Suppresses `SIGPIPE` on writes to a peer that has disconnected, so the write returns a
+//! recoverable [`BrokenPipe`](std::io::ErrorKind::BrokenPipe) error instead of killing the
+//! process, which is the default behaviour of a plain `write`/`send` on a closed socket.
+
+use std::io;
+use std::io::IoSlice;
+use std::os::unix::io::RawFd;
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+const NOSIGNAL: libc::c_int = libc::MSG_NOSIGNAL;
+
+// macOS doesn't support MSG_NOSIGNAL; SIGPIPE is suppressed per-socket instead, via
+// `set_nosigpipe` called once when the connection is established.
+#[cfg(target_os = "macos")]
+const NOSIGNAL: libc::c_int = 0;
+
+/// Sends one chunk of `buf` to `fd` with `SIGPIPE` suppressed, returning the number of bytes
+/// written (which may be less than `buf.len()`), matching the contract of a single `send` call.
+pub(crate) fn send_once(fd: RawFd, buf: &[u8]) -> io::Result<usize>
+{
+    let ret = unsafe
+    {
+        libc::send(fd, buf.as_ptr() as *const libc::c_void, buf.len(), NOSIGNAL)
+    };
+
+    if ret < 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret as usize)
+}
+
+/// Sends `bufs` in a single `sendmsg` call with `SIGPIPE` suppressed, matching the contract of
+/// [`Write::write_vectored`](std::io::Write::write_vectored) (may write less than the total).
+pub(crate) fn send_vectored_once(fd: RawFd, bufs: &[IoSlice<'_>]) -> io::Result<usize>
+{
+    let iovecs: Vec<libc::iovec> = bufs.iter().map(|buf| libc::iovec
+    {
+        iov_base: buf.as_ptr() as *mut libc::c_void,
+        iov_len: buf.len()
+    }).collect();
+
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = iovecs.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = iovecs.len() as _;
+
+    let ret = unsafe { libc::sendmsg(fd, &msg, NOSIGNAL) };
+    if ret < 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret as usize)
+}
+
+/// Sets `SO_NOSIGPIPE` on the socket. Call once at connect/accept time on macOS, where
+/// `MSG_NOSIGNAL` isn't available.
+#[cfg(target_os = "macos")]
+pub(crate) fn set_nosigpipe(fd: RawFd) -> io::Result<()>
+{
+    let enable: libc::c_int = 1;
+    let ret = unsafe
+    {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_NOSIGPIPE,
+            &enable as *const _ as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t
+        )
+    };
+
+    if ret != 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn set_nosigpipe(_fd: RawFd) -> io::Result<()>
+{
+    Ok(())
+}
+
+/// Writes the whole buffer to `fd` via [`send_once`] with `SIGPIPE` suppressed, retrying on
+/// short writes the same way [`Write::write_all`](std::io::Write::write_all) does.
+pub(crate) fn write_all(fd: RawFd, mut buf: &[u8]) -> io::Result<()>
+{
+    while !buf.is_empty()
+    {
+        match send_once(fd, buf)
+        {
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer")),
+            Ok(n) => buf = &buf[n..],
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Peer credential lookups for Unix domain socket connections.
+//!
+//! Lets a server inspect the uid/gid/pid of the process on the other end of a connection
+//! instead of trusting the filesystem permissions of the socket alone.
+
+use std::io;
+use std::mem::{size_of, MaybeUninit};
+use std::os::unix::io::RawFd;
+
+/// Credentials of the process on the other end of a Unix socket connection.
+///
+/// `pid` is only available on platforms that report it through `SO_PEERCRED` (Linux);
+/// it is always `None` on the `getpeereid`-based platforms (macOS/BSD).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UCred
+{
+    pub pid: Option<u32>,
+    pub uid: u32,
+    pub gid: u32
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn peer_cred(fd: RawFd) -> Result<UCred, io::Error>
+{
+    let mut ucred = MaybeUninit::<libc::ucred>::uninit();
+    let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+
+    let ret = unsafe
+    {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            ucred.as_mut_ptr() as *mut libc::c_void,
+            &mut len
+        )
+    };
+
+    if ret != 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+
+    let ucred = unsafe { ucred.assume_init() };
+    Ok(UCred
+    {
+        pid: if ucred.pid > 0 { Some(ucred.pid as u32) } else { None },
+        uid: ucred.uid,
+        gid: ucred.gid
+    })
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+pub(crate) fn peer_cred(fd: RawFd) -> Result<UCred, io::Error>
+{
+    let mut uid = MaybeUninit::<libc::uid_t>::uninit();
+    let mut gid = MaybeUninit::<libc::gid_t>::uninit();
+
+    let ret = unsafe { libc::getpeereid(fd, uid.as_mut_ptr(), gid.as_mut_ptr()) };
+
+    if ret != 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(UCred
+    {
+        pid: None,
+        uid: unsafe { uid.assume_init() },
+        gid: unsafe { gid.assume_init() }
+    })
+}
@@ -1,5 +1,9 @@
+use std::os::unix::io::{AsRawFd, RawFd};
+
 use tokio::net::UnixStream;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncReadExt;
+
+use crate::{fd_passing, no_sigpipe, peer_cred, UCred};
 
 use super::RawListener as IPCServer;
 
@@ -12,6 +16,9 @@ impl Connection
 {
     pub fn new(stream: UnixStream) -> Self
     {
+        // Best-effort: on platforms without MSG_NOSIGNAL, writes to a dead peer still raise
+        // SIGPIPE until this is set, but there's no fallback worth failing the connection over.
+        let _ = no_sigpipe::set_nosigpipe(stream.as_raw_fd());
         Self { stream }
     }
 
@@ -27,11 +34,75 @@ impl Connection
         //     data: message_parts.map(|s| s.to_string()).collect()
         // })
     }
-    
+
+    /// Writes `data` to the socket, retrying on `WouldBlock` until it's writable again, with
+    /// `SIGPIPE` suppressed so a write to a peer that has disconnected returns a recoverable
+    /// [`BrokenPipe`](std::io::ErrorKind::BrokenPipe) error instead of killing the process.
+    async fn write_all_no_sigpipe(&mut self, mut data: &[u8]) -> Result<(), std::io::Error>
+    {
+        let raw_fd = self.stream.as_raw_fd();
+        while !data.is_empty()
+        {
+            self.stream.writable().await?;
+            match no_sigpipe::send_once(raw_fd, data)
+            {
+                Ok(n) => data = &data[n..],
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e)
+            }
+        }
+        Ok(())
+    }
+
     pub async fn send(&mut self, response: impl Into<String>) -> Result<(), std::io::Error>
     {
         let response: String = response.into();
-        self.stream.write_all(&Vec::from(response)).await
+        self.write_all_no_sigpipe(&Vec::from(response)).await
+    }
+
+    /// Returns the uid, gid and (on Linux) pid of the process on the other end of this connection.
+    pub fn peer_cred(&self) -> Result<UCred, std::io::Error>
+    {
+        peer_cred::peer_cred(self.stream.as_raw_fd())
+    }
+
+    /// Sends `data` together with `fds` as `SCM_RIGHTS` ancillary data, handing the open
+    /// file descriptors over to the process on the other end of the connection.
+    ///
+    /// `data` must contain at least one byte; some kernels refuse to carry ancillary data on
+    /// an empty payload.
+    pub async fn send_with_fds(&mut self, data: &[u8], fds: &[RawFd]) -> Result<usize, std::io::Error>
+    {
+        let raw_fd = self.stream.as_raw_fd();
+        loop
+        {
+            self.stream.writable().await?;
+            match fd_passing::send_with_fds(raw_fd, data, fds)
+            {
+                Ok(n) => return Ok(n),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e)
+            }
+        }
+    }
+
+    /// Receives data together with any `SCM_RIGHTS` fds attached to it.
+    ///
+    /// Up to `fd_buf.capacity()` fds are accepted; received fds are owned by the caller, who
+    /// is responsible for closing them.
+    pub async fn recv_with_fds(&mut self, buf: &mut [u8], fd_buf: &mut Vec<RawFd>) -> Result<usize, std::io::Error>
+    {
+        let raw_fd = self.stream.as_raw_fd();
+        loop
+        {
+            self.stream.readable().await?;
+            match fd_passing::recv_with_fds(raw_fd, buf, fd_buf)
+            {
+                Ok(n) => return Ok(n),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e)
+            }
+        }
     }
 }
 
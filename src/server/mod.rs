@@ -2,14 +2,15 @@
 pub mod async_wrapper;
 
 use std::os::unix::net::{ UnixStream, UnixListener };
+use std::os::unix::io::AsRawFd;
 use std::fs::remove_file;
 
 use logs::{info, error};
 
-use crate::SOCKET_PATH;
+use crate::{SOCKET_PATH, UCred, peer_cred, no_sigpipe};
 
 
-/// Data received from the server, it currently only holds the [UnixStream] but it can be expanded to hold more data. 
+/// Data received from the server, it currently only holds the [UnixStream] but it can be expanded to hold more data.
 #[derive(Debug)]
 pub struct StreamData
 {
@@ -17,6 +18,40 @@ pub struct StreamData
 //  ...
 }
 
+impl StreamData
+{
+    fn new(stream: UnixStream) -> Self
+    {
+        // Best-effort: on platforms without MSG_NOSIGNAL, writes to a dead peer still raise
+        // SIGPIPE until this is set, but there's no fallback worth failing the connection over.
+        let _ = no_sigpipe::set_nosigpipe(stream.as_raw_fd());
+        Self { stream }
+    }
+
+    /// Returns the uid, gid and (on Linux) pid of the process on the other end of this connection.
+    pub fn peer_cred(&self) -> Result<UCred, std::io::Error>
+    {
+        peer_cred::peer_cred(self.stream.as_raw_fd())
+    }
+
+    /// Sends `response`, with `SIGPIPE` suppressed so a write to a peer that has disconnected
+    /// returns a recoverable [`BrokenPipe`](std::io::ErrorKind::BrokenPipe) error instead of
+    /// killing the process.
+    pub fn send_raw(&mut self, response: &[u8]) -> Result<(), std::io::Error>
+    {
+        no_sigpipe::write_all(self.stream.as_raw_fd(), response)
+    }
+
+    /// Sends `response`, with `SIGPIPE` suppressed so a write to a peer that has disconnected
+    /// returns a recoverable [`BrokenPipe`](std::io::ErrorKind::BrokenPipe) error instead of
+    /// killing the process.
+    pub fn send(&mut self, response: impl Into<String>) -> Result<(), std::io::Error>
+    {
+        let response: String = response.into();
+        no_sigpipe::write_all(self.stream.as_raw_fd(), response.as_bytes())
+    }
+}
+
 /// Server side communication handler, it is capable of listening for incoming connections and handling the data received.
 ///
 /// It provides two methods to handle the incoming data, these two only differentiates in the way the data is handled. 
@@ -74,7 +109,7 @@ impl RawListener
     ///     let buf = &mut vec![0u8; 512];
     ///     let msg_len = stream_data.stream.read(buf).unwrap();
     ///     let msg = format!("Received {:?}", String::from_utf8_lossy(&buf[..msg_len]));
-    ///     stream_data.stream.write_all(msg.as_bytes()).unwrap();
+    ///     stream_data.send(msg).unwrap();
     /// }
     /// 
     /// fn flow(mut server: Server) -> Result<(), io::Error>
@@ -105,7 +140,7 @@ impl RawListener
     /// fn handle_data(data: &[u8], mut stream_data: StreamData)
     /// {
     ///     let msg = format!("Received {:?}", String::from_utf8_lossy(data));
-    ///     stream_data.stream.write_all(msg.as_bytes()).unwrap();
+    ///     stream_data.send(msg).unwrap();
     /// }
     /// 
     /// fn flow(mut server: Server) -> Result<(), io::Error>
@@ -123,22 +158,19 @@ impl RawListener
     pub fn wait_connection(&mut self) -> Result<StreamData, std::io::Error>
     {
         let (stream, _) = self.listener.accept()?;
-        
-        Ok(StreamData { stream })
+
+        Ok(StreamData::new(stream))
     }
 
     /// Handles the client connection, calling the callback function.
-    /// 
+    ///
     /// A helper function used by [`listen`](Server::listen).
-    fn handle_client(callback: &impl Fn(&mut StreamData), 
+    fn handle_client(callback: &impl Fn(&mut StreamData),
                     stream: UnixStream) -> Result<(), std::io::Error>
     {
-        let mut stream_data: StreamData = StreamData 
-        {
-            stream
-        };
+        let mut stream_data: StreamData = StreamData::new(stream);
 
         callback(&mut stream_data);
-        Ok(())   
+        Ok(())
     }
 }
\ No newline at end of file
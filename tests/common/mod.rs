@@ -1,14 +1,14 @@
-use ipc::{SOCKET_PATH, StreamData};
+use ipc::SOCKET_PATH;
 
 use std::fs;
-use std::{io, io::Write};
+use std::io;
 
 pub fn setup()
 {
     match fs::create_dir_all(SOCKET_PATH)
     {
         Ok(_) => { },
-        Err(e) => 
+        Err(e) =>
         {
             match e.kind()
             {
@@ -19,11 +19,3 @@ pub fn setup()
         }
     }
 }
-
-pub fn callback_fn(data: &[u8], stream_data: &mut StreamData) 
-{
-    let message = String::from_utf8_lossy(data);
-    assert_eq!(message, "Hello, ");
-    print!("{}", message);
-    stream_data.stream.write_all("World!".as_bytes()).unwrap();
-}
@@ -1,3 +1,298 @@
+mod common;
+
+use ipc::{Server, Connection, new_client};
+
+use std::io;
+use std::thread::spawn;
+
+#[test]
+fn peer_cred_reports_the_connecting_processes_uid() -> io::Result<()>
+{
+    common::setup();
+
+    let sock_path = "peer_cred_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let handle = spawn(move || -> io::Result<u32>
+    {
+        let connection = server.wait_connection()?;
+        Ok(connection.peer_cred()?.uid)
+    });
+
+    let stream = new_client(sock_path)?;
+    let _client_connection = Connection::new(stream);
+
+    let server_side_uid = handle.join().expect("server thread panicked")?;
+    let our_uid = unsafe { libc::getuid() };
+
+    assert_eq!(server_side_uid, our_uid);
+    Ok(())
+}
+
+#[test]
+fn send_with_fds_hands_over_a_usable_fd() -> io::Result<()>
+{
+    use std::io::{Read, Write};
+    use std::os::unix::io::{FromRawFd, RawFd};
+
+    common::setup();
+
+    let sock_path = "fd_passing_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let mut pipe_fds = [0 as RawFd; 2];
+    assert_eq!(unsafe { libc::pipe(pipe_fds.as_mut_ptr()) }, 0);
+    let [read_fd, write_fd] = pipe_fds;
+
+    let handle = spawn(move || -> io::Result<RawFd>
+    {
+        let mut connection = server.wait_connection()?;
+        let mut buf = [0u8; 16];
+        let mut fd_buf = Vec::with_capacity(1);
+        connection.recv_with_fds(&mut buf, &mut fd_buf)?;
+        Ok(fd_buf[0])
+    });
+
+    let stream = new_client(sock_path)?;
+    let mut client_connection = Connection::new(stream);
+    client_connection.send_with_fds(b"here", &[read_fd])?;
+
+    let received_fd = handle.join().expect("server thread panicked")?;
+    unsafe { libc::close(read_fd) };
+
+    let mut write_file = unsafe { std::fs::File::from_raw_fd(write_fd) };
+    write_file.write_all(b"payload")?;
+    drop(write_file);
+
+    let mut read_file = unsafe { std::fs::File::from_raw_fd(received_fd) };
+    let mut received = String::new();
+    read_file.read_to_string(&mut received)?;
+
+    assert_eq!(received, "payload");
+    Ok(())
+}
+
+#[test]
+fn recv_with_fds_truncates_instead_of_accepting_an_unwanted_fd() -> io::Result<()>
+{
+    use std::os::unix::io::RawFd;
+
+    common::setup();
+
+    let sock_path = "fd_passing_truncation_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let mut pipe_fds = [0 as RawFd; 2];
+    assert_eq!(unsafe { libc::pipe(pipe_fds.as_mut_ptr()) }, 0);
+    let [read_fd, write_fd] = pipe_fds;
+
+    let handle = spawn(move || -> io::Result<io::Result<()>>
+    {
+        let mut connection = server.wait_connection()?;
+        let mut buf = [0u8; 16];
+        let mut fd_buf = Vec::new(); // capacity 0: caller wants no fds
+        let result = connection.recv_with_fds(&mut buf, &mut fd_buf).map(|_| ());
+        assert!(fd_buf.is_empty());
+        Ok(result)
+    });
+
+    let stream = new_client(sock_path)?;
+    let mut client_connection = Connection::new(stream);
+    client_connection.send_with_fds(b"here", &[read_fd])?;
+    unsafe { libc::close(read_fd); libc::close(write_fd); }
+
+    let result = handle.join().expect("server thread panicked")?;
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn read_timeout_fires_when_the_peer_stalls() -> io::Result<()>
+{
+    use std::time::Duration;
+
+    common::setup();
+
+    let sock_path = "read_timeout_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let handle = spawn(move || -> io::Result<std::io::ErrorKind>
+    {
+        let mut connection = server.wait_connection()?;
+        connection.set_read_timeout(Some(Duration::from_millis(100)))?;
+
+        let mut buf = [0u8; 16];
+        match connection.read_raw(&mut buf)
+        {
+            Ok(_) => panic!("expected a timeout, got data instead"),
+            Err(e) => Ok(e.kind())
+        }
+    });
+
+    // Keep the client connected without sending anything, so the server's read times out
+    // instead of seeing an immediate EOF.
+    let stream = new_client(sock_path)?;
+    let _client_connection = Connection::new(stream);
+
+    let kind = handle.join().expect("server thread panicked")?;
+    assert!(matches!(kind, io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut));
+    Ok(())
+}
+
+#[test]
+fn send_framed_round_trips_and_recv_framed_enforces_the_max_size_guard() -> io::Result<()>
+{
+    let sock_path = "framing_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let handle = spawn(move || -> io::Result<(Vec<u8>, io::ErrorKind)>
+    {
+        let mut connection = server.wait_connection()?;
+
+        let frame = connection.recv_framed(ipc::DEFAULT_MAX_FRAME_SIZE)?;
+        let oversized_err = connection.recv_framed(4).unwrap_err().kind();
+
+        Ok((frame, oversized_err))
+    });
+
+    let stream = new_client(sock_path)?;
+    let mut client_connection = Connection::new(stream);
+    client_connection.send_framed(b"Hello, world!")?;
+    client_connection.send_framed(b"oversized")?;
+
+    let (frame, oversized_err) = handle.join().expect("server thread panicked")?;
+
+    assert_eq!(frame, b"Hello, world!");
+    assert_eq!(oversized_err, io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn abstract_sockets_round_trip_without_touching_the_filesystem() -> io::Result<()>
+{
+    let name = "abstract_socket_test";
+    let mut server = Server::bind_abstract(name)?;
+
+    let handle = spawn(move || -> io::Result<Vec<u8>>
+    {
+        let mut connection = server.wait_connection()?;
+        let mut buf = [0u8; 16];
+        let bytes_read = connection.read_raw(&mut buf)?;
+        Ok(buf[..bytes_read].to_vec())
+    });
+
+    let stream = ipc::new_client_abstract(name)?;
+    let mut client_connection = Connection::new(stream);
+    client_connection.send("Hello, world!")?;
+
+    let received = handle.join().expect("server thread panicked")?;
+    assert_eq!(received, b"Hello, world!");
+    assert!(!std::path::Path::new(&format!("/tmp/{name}")).exists());
+    Ok(())
+}
+
+#[test]
+fn write_vectored_sends_header_and_body_in_one_call() -> io::Result<()>
+{
+    use std::io::IoSlice;
+
+    common::setup();
+
+    let sock_path = "vectored_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let handle = spawn(move || -> io::Result<Vec<u8>>
+    {
+        let mut connection = server.wait_connection()?;
+        let mut buf = [0u8; 16];
+        let bytes_read = connection.read_raw(&mut buf)?;
+        Ok(buf[..bytes_read].to_vec())
+    });
+
+    let stream = new_client(sock_path)?;
+    let mut client_connection = Connection::new(stream);
+    let header = b"head:";
+    let body = b"body";
+    client_connection.write_vectored(&[IoSlice::new(header), IoSlice::new(body)])?;
+
+    let received = handle.join().expect("server thread panicked")?;
+    assert_eq!(received, b"head:body");
+    Ok(())
+}
+
+#[test]
+fn shutdown_write_lets_the_peer_read_remaining_data_as_eof() -> io::Result<()>
+{
+    use std::net::Shutdown;
+
+    common::setup();
+
+    let sock_path = "shutdown_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let handle = spawn(move || -> io::Result<Vec<u8>>
+    {
+        let mut connection = server.wait_connection()?;
+        let mut received = Vec::new();
+        let mut buf = [0u8; 16];
+        loop
+        {
+            let bytes_read = connection.read_raw(&mut buf)?;
+            if bytes_read == 0
+            {
+                break;
+            }
+            received.extend_from_slice(&buf[..bytes_read]);
+        }
+        Ok(received)
+    });
+
+    let stream = new_client(sock_path)?;
+    let mut client_connection = Connection::new(stream);
+    client_connection.send("Hello, world!")?;
+    client_connection.shutdown(Shutdown::Write)?;
+
+    let received = handle.join().expect("server thread panicked")?;
+    assert_eq!(received, b"Hello, world!");
+    Ok(())
+}
+
+#[test]
+fn send_to_a_dropped_peer_returns_broken_pipe_instead_of_killing_the_process() -> io::Result<()>
+{
+    use std::time::Duration;
+
+    common::setup();
+
+    let sock_path = "broken_pipe_test.sock";
+    let mut server = Server::new(sock_path)?;
+
+    let handle = spawn(move || -> io::Result<io::ErrorKind>
+    {
+        let mut connection = server.wait_connection()?;
+
+        // Wait for the client to disconnect, then keep writing until the kernel reports it:
+        // the first write or two after a close can still succeed before the RST is seen.
+        for _ in 0..100
+        {
+            match connection.send("still here?")
+            {
+                Ok(()) => std::thread::sleep(Duration::from_millis(10)),
+                Err(e) => return Ok(e.kind())
+            }
+        }
+        panic!("peer disconnect was never observed");
+    });
+
+    let stream = new_client(sock_path)?;
+    drop(stream);
+
+    let kind = handle.join().expect("server thread panicked")?;
+    assert_eq!(kind, io::ErrorKind::BrokenPipe);
+    Ok(())
+}
+
 // mod common;
 
 // use ipc::*;